@@ -3,15 +3,17 @@ use clap::Parser;
 use std::{
     fs::{self, OpenOptions},
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::ExitCode,
-    str::FromStr,
 };
 
 use toml::Value;
 use toml_const::consts::*;
 
-use crate::package_navi::find_cargo_parent;
+use crate::{
+    error::{self, CliError},
+    package_navi::{find_cargo_parent, resolve_workspace_members, CargoManifest},
+};
 
 /// CLI arguments
 #[derive(Clone, Debug, Parser)]
@@ -25,16 +27,33 @@ pub struct CliArgs {
 pub enum MainSubCommands {
     /// Initialize a new project with boilerplate
     Init(Init),
+
+    /// Repair `.cargo/config.toml` env keys that have drifted out of sync with the manifest
+    Sync(Sync),
 }
 
 /// init subcommand
 #[derive(Clone, Debug, Parser)]
 pub struct Init {
     /// Path to Cargo.toml.
+    ///
+    /// If this points at a workspace manifest (one with a `[workspace]` table
+    /// and no `[package]` table), every member listed under
+    /// `[workspace] members` is initialized in turn. They all share the same
+    /// root `.cargo/config.toml`, since that is the only file a root-level
+    /// `cargo build` reads - but cargo only honors one `[env]` table per
+    /// invocation, so only one member ends up with active env keys there: the
+    /// member that already holds them, or else the first member processed.
+    /// The other members still get their template/debug/deploy files
+    /// and `.gitignore` rules created; run `init` directly against one of them
+    /// (e.g. from inside its own directory) to make cargo pick up its keys.
     #[clap(value_parser)]
     pub manifest_path: String,
 
     /// Set the name prefix for toml files. Uses the manifest package name by default.
+    ///
+    /// Ignored when `manifest_path` points at a workspace manifest, since each
+    /// member contributes its own name.
     #[clap(short, long)]
     pub with_name: Option<String>,
 
@@ -48,29 +67,55 @@ pub struct Init {
     /// Path to generated file, relative to the provided manifest path.
     #[clap(short, long, default_value = "generated.rs")]
     pub generated_file_path: String,
+
+    /// Skip creating or updating .gitignore files.
+    #[clap(long)]
+    pub no_gitignore: bool,
+}
+
+/// sync subcommand
+#[derive(Clone, Debug, Parser)]
+pub struct Sync {
+    /// Path to Cargo.toml.
+    #[clap(value_parser)]
+    pub manifest_path: String,
+
+    /// Name prefix used for the toml files. Uses the manifest package name by default.
+    #[clap(short, long)]
+    pub with_name: Option<String>,
+
+    /// Configuration dir for toml files, relative to the root cargo manifest.
+    #[clap(short, long, default_value = ".config/")]
+    pub config_path: String,
+
+    /// Path to generated file, relative to the provided manifest path.
+    #[clap(short, long, default_value = "generated.rs")]
+    pub generated_file_path: String,
 }
 
 /// Run the CLI
 pub fn run() -> ExitCode {
     let args = CliArgs::parse();
 
-    // we only have one subcommand right now
-    #[allow(irrefutable_let_patterns)]
-    let args = if let MainSubCommands::Init(i) = args.command {
-        i
-    } else {
-        return ExitCode::SUCCESS;
-    };
+    match args.command {
+        MainSubCommands::Init(i) => run_init(i),
+        MainSubCommands::Sync(s) => run_sync(s),
+    }
+}
 
-    let cargo_manifest = match fs::read_to_string(&args.manifest_path) {
-        Ok(f) => f,
+/// Run the `init` subcommand
+fn run_init(args: Init) -> ExitCode {
+    let manifest_path = Path::new(&args.manifest_path);
+
+    let contents = match error::read_to_string(manifest_path) {
+        Ok(c) => c,
         Err(e) => {
             log::error!("Failed to read cargo manifest: {}", e);
             return ExitCode::FAILURE;
         }
     };
 
-    let table: toml::Table = match toml::from_str(&cargo_manifest) {
+    let table = match error::parse_toml(manifest_path, &contents) {
         Ok(t) => t,
         Err(e) => {
             log::error!("Failed to parse manifest into toml: {}", e);
@@ -78,167 +123,471 @@ pub fn run() -> ExitCode {
         }
     };
 
-    // get the package name
-    let t = match table.get("package").and_then(|t| t.get("name")) {
-        Some(t) => t,
-        None => {
-            log::error!("Cargo manifest does not have a package name. The manifest specified may be a workspace.");
+    // a virtual workspace manifest has a [workspace] table but no [package] table
+    if table.contains_key("workspace") && !table.contains_key("package") {
+        let members = match resolve_workspace_members(&manifest_path.to_path_buf()) {
+            Ok(m) => m,
+            Err(e) => {
+                log::error!("Failed to resolve workspace members: {}", e);
+                return ExitCode::FAILURE;
+            }
+        };
 
+        if members.is_empty() {
+            log::error!("Workspace manifest does not list any members.");
             return ExitCode::FAILURE;
         }
-    };
 
-    let mut package_name = match t {
-        Value::String(p) => p.clone(),
-        _ => {
-            log::error!("Cargo package name needs to be a string");
-            return ExitCode::FAILURE;
+        for member in members {
+            log::info!("Initializing workspace member: {}", member.display());
+
+            match init_package(
+                &member,
+                None, // each member keeps its own package name
+                &args.config_path,
+                &args.generated_file_path,
+                args.no_gitignore,
+                // every member shares the same root `.cargo/config.toml`; let
+                // init_package skip (rather than clobber) a member that finds
+                // another member's env keys already there
+                true,
+            ) {
+                ExitCode::SUCCESS => (),
+                failure => return failure,
+            }
         }
-    };
 
-    // override the package name if it is passed
-    if let Some(name_override) = args.with_name {
-        package_name = name_override.clone();
+        return ExitCode::SUCCESS;
+    }
+
+    init_package(
+        manifest_path,
+        args.with_name.as_deref(),
+        &args.config_path,
+        &args.generated_file_path,
+        args.no_gitignore,
+        false,
+    )
+}
+
+/// Run the full init sequence (template/debug/deploy file creation, `.cargo/config.toml`
+/// env insertion, `.gitignore` rule update, unless `no_gitignore` is set) for a single
+/// package manifest.
+///
+/// This is safe to call again on a manifest that has already been initialized: existing
+/// template/debug/deploy files are left untouched and the managed `.gitignore` blocks are
+/// rewritten in place instead of duplicated.
+///
+/// `workspace_member` should be set when initializing one member of a workspace in a
+/// loop. Cargo discovers `.cargo/config.toml` by walking up from the *invocation*
+/// directory, not the manifest, so a root-level `cargo build` only ever reads the
+/// workspace root's config - there is no member-local file for cargo to merge in.
+/// Since cargo also only honors one `[env]` table per invocation, at most one member
+/// can have its env keys active there at a time; when another member already owns
+/// them, this skips (and logs why) rather than clobbering that member's keys.
+fn init_package(
+    manifest_path: &Path,
+    with_name: Option<&str>,
+    config_path: &str,
+    generated_file_path: &str,
+    no_gitignore: bool,
+    workspace_member: bool,
+) -> ExitCode {
+    match try_init_package(
+        manifest_path,
+        with_name,
+        config_path,
+        generated_file_path,
+        no_gitignore,
+        workspace_member,
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{}", e);
+            ExitCode::FAILURE
+        }
     }
+}
+
+fn try_init_package(
+    manifest_path: &Path,
+    with_name: Option<&str>,
+    config_path: &str,
+    generated_file_path: &str,
+    no_gitignore: bool,
+    workspace_member: bool,
+) -> Result<(), CliError> {
+    let package_name = resolve_package_name(manifest_path, with_name)?;
 
     let template_name = format!("{}.template.toml", package_name);
     let debug_name = format!("{}.debug.toml", package_name);
     let deploy_name = format!("{}.deploy.toml", package_name);
 
-    // write env variables into cargo config
-    let (cargo_project_root, cargo_dot_config_file, toml_config_dir, generated_file) = {
-        let mut cargo_project_directory = PathBuf::from_str(&args.manifest_path)
-            .unwrap()
-            .canonicalize()
-            .unwrap()
-            .parent()
-            .expect("failed to get cargo manifest directory")
-            .to_owned();
+    let paths = resolve_package_paths(manifest_path, config_path, generated_file_path)?;
+    let cargo_project_root = paths.cargo_project_root;
+    let cargo_dot_config_file = paths.cargo_dot_config_file;
+    let toml_config_dir = paths.toml_config_dir;
+    let generated_file = paths.generated_file;
+    let relative_root = paths.relative_root;
 
-        let mut generated_file = cargo_project_directory.clone();
-        generated_file.push(&args.generated_file_path);
-        generated_file = generated_file
-            .strip_prefix(&cargo_project_directory)
-            .unwrap()
-            .to_path_buf();
+    let mut config_file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .open(&cargo_dot_config_file)
+        .map_err(|source| CliError::Io {
+            path: cargo_dot_config_file.clone(),
+            source,
+        })?;
 
-        let mut toml_config_dir = cargo_project_directory.clone();
-        toml_config_dir.push(&args.config_path);
-        toml_config_dir = toml_config_dir
-            .strip_prefix(&cargo_project_directory)
-            .unwrap()
-            .to_path_buf();
-
-        // the .cargo/config.toml lives in the project root (top level dir that contains a Cargo.toml file)
-        let mut cargo_config_dir = match find_cargo_parent(&cargo_project_directory) {
-            Some(root) => {
-                let root_parent = root.parent().unwrap().to_path_buf();
-                cargo_project_directory = root_parent.clone();
-                root_parent
-            }
-            None => cargo_project_directory.clone(),
-        };
+    let mut config_contents = String::new();
+    config_file
+        .read_to_string(&mut config_contents)
+        .map_err(|source| CliError::Io {
+            path: cargo_dot_config_file.clone(),
+            source,
+        })?;
+
+    let mut config_contents = error::parse_toml(&cargo_dot_config_file, &config_contents)?;
+
+    // Cargo only honors one `[env]` table per build invocation, so a workspace with
+    // several members can only have one of them active in the shared root
+    // `.cargo/config.toml` at a time. If another member already claimed it, leave
+    // its keys alone instead of silently overwriting them.
+    let owned_by_another_member = workspace_member
+        && matches!(
+            config_contents
+                .get("env")
+                .and_then(Value::as_table)
+                .and_then(|t| t.get(TEMPLATE_ENV))
+                .and_then(Value::as_str),
+            Some(existing) if existing != template_name
+        );
+
+    if owned_by_another_member {
+        log::warn!(
+            "{} already holds toml_const env keys for another workspace member; cargo \
+             only honors one [env] table per build invocation, so `{}`'s keys were left \
+             out. Run `init` directly against its manifest (e.g. from inside its own \
+             directory) to activate it there instead.",
+            cargo_dot_config_file.display(),
+            package_name
+        );
+    } else {
+        update_config_toml(
+            &mut config_contents,
+            &template_name,
+            &debug_name,
+            &deploy_name,
+            toml_config_dir.to_str().unwrap(),
+            generated_file.to_str().unwrap(),
+            &relative_root,
+        )?;
+
+        // writing env vars to config.toml
+        let mut config_file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&cargo_dot_config_file)
+            .map_err(|source| CliError::Io {
+                path: cargo_dot_config_file.clone(),
+                source,
+            })?;
+
+        let serialized = toml::to_string_pretty(&config_contents).map_err(|e| {
+            CliError::Other(format!("{}: {}", cargo_dot_config_file.display(), e))
+        })?;
+
+        config_file
+            .write_all(serialized.as_bytes())
+            .map_err(|source| CliError::Io {
+                path: cargo_dot_config_file.clone(),
+                source,
+            })?;
+    }
 
-        cargo_config_dir.push(".cargo");
+    // create files with boilerplate, leaving any that already exist untouched
+    create_config_toml_files(
+        &cargo_project_root,
+        &toml_config_dir,
+        &template_name,
+        &debug_name,
+        &deploy_name,
+    )?;
 
-        // println!("{:?}", cargo_config_dir);
+    if no_gitignore {
+        return Ok(());
+    }
 
-        fs::create_dir_all(&cargo_config_dir).unwrap();
+    // add rules to root gitignore
+    let mut config_dir = cargo_project_root.clone();
+    config_dir.push(config_path);
+    let mut generated_dir = manifest_path.to_path_buf();
+    generated_dir.pop();
+    generated_dir.push(generated_file_path);
 
-        cargo_config_dir.push("config.toml");
-        (
-            cargo_project_directory,
-            cargo_config_dir,
-            toml_config_dir,
-            generated_file,
-        )
-    };
+    update_gitignore_file(&config_dir, &generated_dir, &template_name)?;
 
-    let relative_root = {
-        let base = PathBuf::from(&args.manifest_path).canonicalize().unwrap();
-        let delta = base
-            .strip_prefix(&cargo_project_root)
-            .unwrap()
-            .iter()
-            .count();
+    Ok(())
+}
 
-        let res: String = (1..delta).into_iter().map(|_| "../").collect();
+/// Run the `sync` subcommand
+fn run_sync(args: Sync) -> ExitCode {
+    let manifest_path = Path::new(&args.manifest_path);
 
-        res
-    };
+    match try_sync_package(
+        manifest_path,
+        args.with_name.as_deref(),
+        &args.config_path,
+        &args.generated_file_path,
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    // println!("relative root: {:?}", relative_root);
+/// Re-derive the expected `.cargo/config.toml` env values for a manifest and rewrite
+/// only the keys that drifted, reporting each one that changed. Unlike `init`, this
+/// requires a `.cargo/config.toml` to already exist: `sync` repairs drift, it does
+/// not set a package up for the first time.
+fn try_sync_package(
+    manifest_path: &Path,
+    with_name: Option<&str>,
+    config_path: &str,
+    generated_file_path: &str,
+) -> Result<(), CliError> {
+    let package_name = resolve_package_name(manifest_path, with_name)?;
 
-    let mut config_file = OpenOptions::new()
-        .create(true)
-        .read(true)
-        .write(true)
-        .open(&cargo_dot_config_file)
-        .unwrap();
+    let template_name = format!("{}.template.toml", package_name);
+    let debug_name = format!("{}.debug.toml", package_name);
+    let deploy_name = format!("{}.deploy.toml", package_name);
 
-    let mut config_contents = String::new();
-    config_file.read_to_string(&mut config_contents).unwrap();
+    let paths = resolve_package_paths(manifest_path, config_path, generated_file_path)?;
+
+    if !paths.cargo_dot_config_file.is_file() {
+        return Err(CliError::Other(format!(
+            "{} does not exist yet; run `init` before `sync`",
+            paths.cargo_dot_config_file.display()
+        )));
+    }
+
+    let contents = error::read_to_string(&paths.cargo_dot_config_file)?;
+    let mut config_contents = error::parse_toml(&paths.cargo_dot_config_file, &contents)?;
 
-    let mut config_contents: toml::Table = toml::from_str(&config_contents).unwrap();
+    let before = config_contents
+        .get("env")
+        .and_then(Value::as_table)
+        .cloned()
+        .unwrap_or_default();
 
-    match update_config_toml(
+    update_config_toml(
         &mut config_contents,
         &template_name,
         &debug_name,
         &deploy_name,
-        toml_config_dir.to_str().unwrap(),
-        generated_file.to_str().unwrap(),
-        &relative_root,
-    ) {
-        Ok(_) => (),
-        Err(e) => {
-            log::error!("{}", e);
-            return ExitCode::FAILURE;
-        }
+        paths.toml_config_dir.to_str().unwrap(),
+        paths.generated_file.to_str().unwrap(),
+        &paths.relative_root,
+    )?;
+
+    let after = config_contents
+        .get("env")
+        .and_then(Value::as_table)
+        .cloned()
+        .unwrap_or_default();
+
+    let changed_keys = managed_env_keys_that_changed(&before, &after);
+
+    if changed_keys.is_empty() {
+        log::info!(
+            "{} is already up to date",
+            paths.cargo_dot_config_file.display()
+        );
+        return Ok(());
     }
 
-    // writing env vars to config.toml
-    let mut config_file = OpenOptions::new()
+    for key in &changed_keys {
+        log::info!("{}: updated {}", paths.cargo_dot_config_file.display(), key);
+    }
+
+    let serialized = toml::to_string_pretty(&config_contents).map_err(|e| {
+        CliError::Other(format!(
+            "{}: {}",
+            paths.cargo_dot_config_file.display(),
+            e
+        ))
+    })?;
+
+    let mut file = OpenOptions::new()
         .write(true)
         .truncate(true)
-        .open(&cargo_dot_config_file)
-        .unwrap();
+        .open(&paths.cargo_dot_config_file)
+        .map_err(|source| CliError::Io {
+            path: paths.cargo_dot_config_file.clone(),
+            source,
+        })?;
+
+    file.write_all(serialized.as_bytes())
+        .map_err(|source| CliError::Io {
+            path: paths.cargo_dot_config_file,
+            source,
+        })?;
 
-    config_file
-        .write_all(toml::to_string_pretty(&config_contents).unwrap().as_bytes())
-        .unwrap();
+    Ok(())
+}
 
-    // create files with boilerplate
-    match create_config_toml_files(
-        &cargo_project_root,
-        &toml_config_dir,
-        &template_name,
-        &debug_name,
-        &deploy_name,
-    ) {
-        Ok(_) => (),
-        Err(e) => {
-            log::error!("Failed to create toml config files: {}", e);
-            return ExitCode::FAILURE;
+/// Compare the managed env keys between two `[env]` tables, returning those whose
+/// value changed (or that are missing from `after`, which should not happen since
+/// `update_config_toml` always writes all of them).
+fn managed_env_keys_that_changed(before: &toml::Table, after: &toml::Table) -> Vec<&'static str> {
+    [
+        TEMPLATE_ENV,
+        DEBUG_ENV,
+        DEPLOY_ENV,
+        CONFIG_PATH_ENV,
+        GENERATED_FILE_PATH_ENV,
+    ]
+    .into_iter()
+    .filter(|key| before.get(*key) != after.get(*key))
+    .collect()
+}
+
+/// Canonicalize `path`, reporting which path failed instead of panicking.
+fn canonicalize(path: &Path) -> Result<PathBuf, CliError> {
+    path.canonicalize().map_err(|source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Strip `base` from `path`, reporting both paths instead of panicking.
+fn strip_prefix(path: &Path, base: &Path) -> Result<PathBuf, CliError> {
+    path.strip_prefix(base)
+        .map(|p| p.to_path_buf())
+        .map_err(|e| {
+            CliError::Other(format!(
+                "{} is not nested under {}: {}",
+                path.display(),
+                base.display(),
+                e
+            ))
+        })
+}
+
+/// Resolve the package name to use for a manifest: the name override if one was
+/// passed, otherwise the manifest's own (possibly workspace-inherited) name.
+fn resolve_package_name(manifest_path: &Path, with_name: Option<&str>) -> Result<String, CliError> {
+    if let Some(name_override) = with_name {
+        return Ok(name_override.to_owned());
+    }
+
+    CargoManifest::from_path(manifest_path)?.package.ok_or_else(|| {
+        CliError::Other(format!(
+            "{}: manifest does not have a resolvable package name (it may be a workspace, \
+             or its inherited name could not be found)",
+            manifest_path.display()
+        ))
+    })
+}
+
+/// Paths derived from a manifest's location that `init` and `sync` both need: the
+/// project root, the `.cargo/config.toml` that holds the managed env keys, and the
+/// config/generated file paths relative to the project root.
+struct ResolvedPaths {
+    cargo_project_root: PathBuf,
+    cargo_dot_config_file: PathBuf,
+    toml_config_dir: PathBuf,
+    generated_file: PathBuf,
+    /// `"../"` repeated once per directory between the project root and the
+    /// manifest, so env values stay correct no matter how deeply nested the
+    /// manifest is.
+    relative_root: String,
+}
+
+fn resolve_package_paths(
+    manifest_path: &Path,
+    config_path: &str,
+    generated_file_path: &str,
+) -> Result<ResolvedPaths, CliError> {
+    let (mut cargo_project_root, toml_config_dir, generated_file) = {
+        let cargo_project_directory = canonicalize(manifest_path)?
+            .parent()
+            .ok_or_else(|| {
+                CliError::Other(format!(
+                    "{}: failed to get cargo manifest directory",
+                    manifest_path.display()
+                ))
+            })?
+            .to_owned();
+
+        let mut generated_file = cargo_project_directory.clone();
+        generated_file.push(generated_file_path);
+        let generated_file = strip_prefix(&generated_file, &cargo_project_directory)?;
+
+        let mut toml_config_dir = cargo_project_directory.clone();
+        toml_config_dir.push(config_path);
+        let toml_config_dir = strip_prefix(&toml_config_dir, &cargo_project_directory)?;
+
+        (cargo_project_directory, toml_config_dir, generated_file)
+    };
+
+    // the .cargo/config.toml lives in the project root (top level dir that contains
+    // a Cargo.toml file), found by walking up from the manifest. This is the single
+    // file a root-level `cargo build` actually reads: cargo discovers
+    // `.cargo/config.toml` by walking up from the *invocation* directory, not the
+    // manifest, so a file living in a workspace member's own directory is only ever
+    // picked up when cargo is invoked from inside that member - never when building
+    // the workspace as a whole from its root. There is no such thing as a
+    // member-local config.toml that gets merged in on top; `init` and `sync` both
+    // resolve to this same shared file.
+    let mut cargo_config_dir = match find_cargo_parent(&cargo_project_root) {
+        Some(root) => {
+            let root_parent = root
+                .parent()
+                .ok_or_else(|| {
+                    CliError::Other(format!(
+                        "{}: workspace manifest has no parent directory",
+                        root.display()
+                    ))
+                })?
+                .to_path_buf();
+            cargo_project_root = root_parent.clone();
+            root_parent
         }
+        None => cargo_project_root.clone(),
     };
 
-    // add rules to root gitignore
-    let mut config_dir = cargo_project_root.clone();
-    config_dir.push(&args.config_path);
-    let mut generated_dir = PathBuf::from(args.manifest_path);
-    generated_dir.pop();
-    generated_dir.push(&args.generated_file_path);
+    cargo_config_dir.push(".cargo");
 
-    match update_gitignore_file(&config_dir, &generated_dir, &template_name) {
-        Ok(_) => (),
-        Err(e) => {
-            log::error!("Unable to update .gitignore: {}", e);
-            return ExitCode::FAILURE;
-        }
-    }
+    fs::create_dir_all(&cargo_config_dir).map_err(|source| CliError::Io {
+        path: cargo_config_dir.clone(),
+        source,
+    })?;
+
+    cargo_config_dir.push("config.toml");
+    let cargo_dot_config_file = cargo_config_dir;
+
+    let relative_root = {
+        let base = canonicalize(manifest_path)?;
+        let delta = base
+            .strip_prefix(&cargo_project_root)
+            .map_err(|e| CliError::Other(e.to_string()))?
+            .iter()
+            .count();
+
+        (1..delta).into_iter().map(|_| "../").collect::<String>()
+    };
 
-    ExitCode::SUCCESS
+    Ok(ResolvedPaths {
+        cargo_project_root,
+        cargo_dot_config_file,
+        toml_config_dir,
+        generated_file,
+        relative_root,
+    })
 }
 
 /// Update the .cargo/config.toml table
@@ -250,7 +599,7 @@ fn update_config_toml(
     config_path: &str,
     generated_path: &str,
     relative_root: &str,
-) -> Result<(), String> {
+) -> Result<(), CliError> {
     let actual_config_path = format!("{}{}", relative_root, config_path);
 
     match toml.get_mut("env") {
@@ -265,7 +614,9 @@ fn update_config_toml(
                     generated_path,
                 );
             } else {
-                return Err(format!("key \"env\" not defined as a table"));
+                return Err(CliError::Other(
+                    "key \"env\" not defined as a table".to_string(),
+                ));
             }
         }
         None => {
@@ -307,20 +658,28 @@ fn insert_into_env(
     );
 }
 
-/// Creates the boilerplate toml config files that will be used for codegen
+/// Creates the boilerplate toml config files that will be used for codegen.
+///
+/// A file that already exists is left untouched rather than erroring out, so running
+/// `init` again on a manifest that has already been set up (or one of its manually
+/// edited files) is safe.
 fn create_config_toml_files(
     project_root: &PathBuf,
     config_path: &PathBuf,
     template: &str,
     debug: &str,
     deploy: &str,
-) -> Result<(), String> {
-    fs::create_dir_all({
+) -> Result<(), CliError> {
+    let config_dir = {
         let mut root = project_root.clone();
         root.push(config_path);
         root
-    })
-    .unwrap();
+    };
+
+    fs::create_dir_all(&config_dir).map_err(|source| CliError::Io {
+        path: config_dir,
+        source,
+    })?;
 
     let paths = [template, debug, deploy];
 
@@ -329,85 +688,127 @@ fn create_config_toml_files(
         new_path.push(config_path);
         new_path.push(path);
 
-        // println  !("new path: {:?}", new_path);
+        if new_path.is_file() {
+            log::info!("{} already exists, leaving it as is", new_path.display());
+            continue;
+        }
 
         let mut file = OpenOptions::new()
             .create(true)
             .write(true)
-            .read(true)
             .open(&new_path)
-            .unwrap();
-
-        let mut contents = String::new();
-        let contents_len = file.read_to_string(&mut contents).unwrap();
-
-        if contents_len != 0 {
-            return Err("Config files already exist".to_string());
-        }
-
-        file.write(CONFIG_TOML_BOILERPLATE.as_bytes()).unwrap();
+            .map_err(|source| CliError::Io {
+                path: new_path.clone(),
+                source,
+            })?;
+
+        file.write(CONFIG_TOML_BOILERPLATE.as_bytes())
+            .map_err(|source| CliError::Io {
+                path: new_path,
+                source,
+            })?;
     }
 
     Ok(())
 }
 
-/// Create or update the gitignore files with new rules
+/// Create or update the gitignore files with new rules.
 /// This will create/update 2 gitignores:
 /// - .config gitignore (for ignoring non-template toml files)
 /// - package gitignore local to MANIFEST_PATH, for ignoring the generated file
+///
+/// Both files are matched by a `# added by {CARGO_PKG_NAME}` / `# end {CARGO_PKG_NAME}
+/// managed block` marker pair: a second run rewrites the managed block in place
+/// instead of appending a duplicate.
 fn update_gitignore_file(
     config_dir: &PathBuf,          // path to .config/
     generated_file_path: &PathBuf, // path to generated file
     template_name: &str,
-) -> Result<(), String> {
+) -> Result<(), CliError> {
     const GITIGNORE: &'static str = ".gitignore";
 
-    // dbg!(config_dir);
-    // dbg!(generated_file_path);
-    // dbg!(template_name);
-
-    let generated_file_name = generated_file_path.file_name().unwrap();
+    let generated_file_name = generated_file_path.file_name().ok_or_else(|| {
+        CliError::Other(format!(
+            "{}: generated file path has no file name",
+            generated_file_path.display()
+        ))
+    })?;
 
-    let generated_rules = format!(
-        "\n\n# added by {}\n{}\n",
-        env!("CARGO_PKG_NAME"),
-        generated_file_name.to_str().unwrap_or(""),
-    );
+    let generated_rules = format!("{}\n", generated_file_name.to_str().unwrap_or(""));
 
     let mut path = generated_file_path.clone();
     path.pop();
     path.push(GITIGNORE);
 
-    // update .gitignore for generated file
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)
-        .unwrap();
+    upsert_managed_block(&path, &generated_rules)?;
 
-    file.write(generated_rules.as_bytes())
-        .map_err(|e| e.to_string())?;
-
-    let config_rules = format!(
-        "# added by {}\n*.toml\n!{}",
-        env!("CARGO_PKG_NAME"),
-        template_name
-    );
+    let config_rules = format!("*.toml\n!{}\n", template_name);
 
     let mut path = config_dir.clone();
-    // path.push(config_path);
     path.push(GITIGNORE);
 
-    // update .gitignore for toml files
+    upsert_managed_block(&path, &config_rules)?;
+
+    Ok(())
+}
+
+/// Write `content` into the gitignore at `path`, wrapped in a `# added by
+/// {CARGO_PKG_NAME}` / `# end {CARGO_PKG_NAME} managed block` marker pair.
+///
+/// A second run replaces only the text between an existing marker pair in place,
+/// rather than appending a duplicate. Content before the start marker *and* content
+/// after the end marker are both preserved, so user-authored rules can sit on either
+/// side of the managed block. A file written by an older version of this function
+/// that only wrote a start marker (no end marker) is also recognized, and its block
+/// - which ran to EOF - is replaced rather than left to stack a second one underneath.
+fn upsert_managed_block(path: &Path, content: &str) -> Result<(), CliError> {
+    const MARKER_START: &str = concat!("# added by ", env!("CARGO_PKG_NAME"));
+    const MARKER_END: &str = concat!("# end ", env!("CARGO_PKG_NAME"), " managed block");
+
+    let block = format!("\n\n{}\n{}{}\n", MARKER_START, content, MARKER_END);
+
+    let existing = if path.is_file() {
+        error::read_to_string(path)?
+    } else {
+        String::new()
+    };
+
+    let new_contents = match (existing.find(MARKER_START), existing.find(MARKER_END)) {
+        (Some(start), Some(end)) if end >= start => {
+            // `block` always ends in the newline that follows MARKER_END, so the
+            // newline immediately after the existing end marker is that same
+            // separator from the previous run, not user content - drop just that
+            // one before re-appending the rest, or the file grows a blank line
+            // every run.
+            let tail_start = end + MARKER_END.len();
+            let tail = &existing[tail_start..];
+            let tail = tail.strip_prefix('\n').unwrap_or(tail);
+            format!("{}{}{}", existing[..start].trim_end(), block, tail)
+        }
+        (Some(start), None) => {
+            // legacy format written before the end marker existed: the managed
+            // block ran to EOF, so replacing from the start marker onward covers
+            // it exactly.
+            format!("{}{}", existing[..start].trim_end(), block)
+        }
+        _ => format!("{}{}", existing, block),
+    };
+
     let mut file = OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
         .open(path)
-        .unwrap();
-
-    file.write(config_rules.as_bytes())
-        .map_err(|e| e.to_string())?;
+        .map_err(|source| CliError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+    file.write_all(new_contents.as_bytes())
+        .map_err(|source| CliError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
 
     Ok(())
 }
@@ -0,0 +1,93 @@
+//! Error types and span-aware diagnostics shared by the CLI subcommands.
+//!
+//! Every helper that touches disk or parses toml returns a [`CliError`] instead of
+//! panicking, so a malformed manifest or config file produces a message pointing at
+//! the offending file (and, for parse errors, the exact span) rather than an
+//! unwrap backtrace.
+
+use std::{fmt, io, path::Path, path::PathBuf};
+
+/// An error encountered while running a subcommand.
+#[derive(Debug)]
+pub enum CliError {
+    /// Reading or writing a file failed.
+    Io { path: PathBuf, source: io::Error },
+    /// A file failed to parse as toml. `diagnostic` already contains a
+    /// span-aware, human readable rendering of the failure.
+    TomlParse { path: PathBuf, diagnostic: String },
+    /// Anything else, carrying its own fully formed message.
+    Other(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            CliError::TomlParse { path, diagnostic } => {
+                write!(f, "failed to parse {}\n{}", path.display(), diagnostic)
+            }
+            CliError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<String> for CliError {
+    fn from(msg: String) -> Self {
+        CliError::Other(msg)
+    }
+}
+
+/// Read `path` to a string, wrapping any IO failure in a [`CliError`].
+pub fn read_to_string(path: &Path) -> Result<String, CliError> {
+    std::fs::read_to_string(path).map_err(|source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Parse the contents of `path` (already read into `contents`) as a toml table,
+/// rendering a span-aware diagnostic on failure.
+pub fn parse_toml(path: &Path, contents: &str) -> Result<toml::Table, CliError> {
+    toml::from_str(contents).map_err(|e| CliError::TomlParse {
+        path: path.to_path_buf(),
+        diagnostic: render_toml_error(contents, &e),
+    })
+}
+
+/// Build a diagnostic for a toml parse error: the error message, the line/column
+/// it starts at, and a snippet of the source line with a caret under the column.
+pub fn render_toml_error(source: &str, error: &toml::de::Error) -> String {
+    let Some(span) = error.span() else {
+        return error.message().to_string();
+    };
+
+    let (line, column) = line_col(source, span.start);
+    let snippet = source.lines().nth(line).unwrap_or("");
+    let caret = format!("{}^", " ".repeat(column));
+
+    format!(
+        "{}\n  --> line {}, column {}\n  {}\n  {}",
+        error.message(),
+        line + 1,
+        column + 1,
+        snippet,
+        caret
+    )
+}
+
+/// Translate a byte offset into a 0-indexed (line, column) pair.
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 0;
+    let mut last_newline = 0;
+
+    for (i, c) in source[..offset.min(source.len())].char_indices() {
+        if c == '\n' {
+            line += 1;
+            last_newline = i + c.len_utf8();
+        }
+    }
+
+    (line, offset.saturating_sub(last_newline))
+}
@@ -1,6 +1,7 @@
 use std::process::ExitCode;
 
 mod cli;
+mod error;
 mod package_navi;
 
 fn main() -> ExitCode {
@@ -1,39 +1,132 @@
 //! Stuff for looking around cargo packages
 
-use std::{fs, path::PathBuf, str::FromStr};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::error::{self, CliError};
+
+/// Resolve the manifest paths of every member of a workspace.
+///
+/// `workspace_manifest` must point at a `Cargo.toml` that declares a `[workspace]`
+/// table. Member entries ending in `/*` are expanded by scanning the corresponding
+/// directory and keeping only the subdirectories that themselves contain a
+/// `Cargo.toml`, mirroring how cargo itself enumerates workspace members. A
+/// malformed workspace manifest is reported with its path and span via
+/// [`error::parse_toml`].
+pub fn resolve_workspace_members(workspace_manifest: &PathBuf) -> Result<Vec<PathBuf>, CliError> {
+    let workspace_dir = workspace_manifest
+        .parent()
+        .ok_or_else(|| CliError::Other("workspace manifest has no parent directory".to_string()))?
+        .to_path_buf();
+
+    let contents = error::read_to_string(workspace_manifest)?;
+    let table = error::parse_toml(workspace_manifest, &contents)?;
+
+    let members = table
+        .get("workspace")
+        .and_then(|w| w.get("members"))
+        .and_then(|m| m.as_array())
+        .ok_or_else(|| {
+            CliError::Other("workspace manifest has no [workspace] members array".to_string())
+        })?;
+
+    let mut manifests = Vec::new();
+
+    for member in members {
+        let pattern = member
+            .as_str()
+            .ok_or_else(|| CliError::Other("workspace member entries must be strings".to_string()))?;
+
+        if let Some(prefix) = pattern.strip_suffix("/*") {
+            let glob_dir = workspace_dir.join(prefix);
+
+            for entry in fs::read_dir(&glob_dir).map_err(|source| CliError::Io {
+                path: glob_dir.clone(),
+                source,
+            })? {
+                let path = entry
+                    .map_err(|source| CliError::Io {
+                        path: glob_dir.clone(),
+                        source,
+                    })?
+                    .path();
+
+                if path.is_dir() && path.join("Cargo.toml").is_file() {
+                    manifests.push(path.join("Cargo.toml"));
+                }
+            }
+        } else {
+            let manifest = workspace_dir.join(pattern).join("Cargo.toml");
+
+            if manifest.is_file() {
+                manifests.push(manifest);
+            }
+        }
+    }
+
+    manifests.sort();
+    Ok(manifests)
+}
 
 use toml::Value;
 
 /// Cargo manifest feature struct
 #[derive(Clone, Debug)]
 #[allow(unused)]
-struct CargoManifest {
-    /// Package name
-    package: Option<String>,
+pub(crate) struct CargoManifest {
+    /// Package name, as a literal string. `None` if the manifest has no
+    /// `[package]` table, or if its name could not be resolved.
+    pub(crate) package: Option<String>,
+    /// Set by `from_str` when `package.name` is declared as `name.workspace = true`
+    /// rather than a literal string. `from_path` resolves this flag away by reading
+    /// the name from the enclosing workspace manifest.
+    package_name_inherited: bool,
     /// If manifest defines a workspace
-    workspace: bool,
-    /// Binary names, if any
-    binaries: Option<Vec<String>>,
-    /// Library name, if any
-    library: Option<String>,
+    pub(crate) workspace: bool,
+    /// Binary target names: explicit `[[bin]]` entries plus, when `from_path`
+    /// resolved this manifest, targets cargo would auto-detect from the `src/`
+    /// layout (see its docs).
+    pub(crate) binaries: Vec<String>,
+    /// Library target name, explicit `[lib] name` or, when `from_path` resolved
+    /// this manifest, auto-detected from a `src/lib.rs`.
+    pub(crate) library: Option<String>,
 }
 
 impl FromStr for CargoManifest {
     type Err = String;
 
-    /// Read in the contents of a Cargo.toml file into the struct
+    /// Read in the contents of a Cargo.toml file into the struct.
+    ///
+    /// This only looks at what is written in the manifest itself: a
+    /// `name.workspace = true` package name is recorded via `package_name_inherited`
+    /// rather than resolved, and targets that cargo would auto-detect from the
+    /// `src/` layout are not filled in. Use `from_path` for that.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let table = toml::Table::from_str(s).map_err(|e| e.to_string())?;
+        Ok(Self::from_table(table))
+    }
+}
+
+impl CargoManifest {
+    /// Build a manifest from an already-parsed toml table. Never fails: the only
+    /// way constructing a `CargoManifest` can fail is the toml parse itself, which
+    /// both `from_str` and `from_path` handle before calling this.
+    fn from_table(table: toml::Table) -> Self {
+        let mut package_name_inherited = false;
 
         let package = table
             .get("package")
             .and_then(|p| p.get("name"))
-            .and_then(|name| {
-                if let Value::String(n) = name {
-                    Some(n.clone())
-                } else {
+            .and_then(|name| match name {
+                Value::String(n) => Some(n.clone()),
+                Value::Table(t) if matches!(t.get("workspace"), Some(Value::Boolean(true))) => {
+                    package_name_inherited = true;
                     None
                 }
+                _ => None,
             });
 
         let workspace = match table.get("workspace") {
@@ -41,49 +134,117 @@ impl FromStr for CargoManifest {
             None => false,
         };
 
-        let binaries = table.get("bin").and_then(|t| {
-            if let Value::Array(bins) = t {
-                let bin_names = bins
-                    .iter()
-                    .map(|inner| {
-                        let name = inner
-                            .get("name")
-                            .expect("each binary target should have a name");
-
-                        if let Value::String(name) = name {
-                            name.clone()
-                        } else {
-                            panic!("binary target should be a string") // this branch should not be taken
-                        }
+        let binaries = table
+            .get("bin")
+            .and_then(|t| t.as_array())
+            .map(|bins| {
+                bins.iter()
+                    .filter_map(|inner| match inner.get("name") {
+                        Some(Value::String(name)) => Some(name.clone()),
+                        _ => None,
                     })
-                    .collect::<Vec<_>>();
-
-                Some(bin_names)
-            } else {
-                None
-            }
-        });
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
 
-        let library = table.get("lib").and_then(|t| match t.get("name") {
-            Some(v) => {
-                if let Value::String(n) = v {
-                    Some(n.clone())
-                } else {
-                    None
-                }
-            }
-            None => None,
-        });
+        let library = table
+            .get("lib")
+            .and_then(|t| t.get("name"))
+            .and_then(|v| match v {
+                Value::String(n) => Some(n.clone()),
+                _ => None,
+            });
 
-        Ok(Self {
+        Self {
             package,
+            package_name_inherited,
             workspace,
             binaries,
             library,
-        })
+        }
+    }
+
+    /// Read a Cargo.toml file, resolving what `from_str`/`from_table` alone cannot
+    /// because they have no knowledge of the manifest's location on disk:
+    /// - `name.workspace = true` is resolved by walking up to the enclosing
+    ///   workspace manifest and reading `[workspace.package] name`.
+    /// - `[[bin]]`/`[lib]` targets that cargo auto-detects from the standard
+    ///   `src/main.rs`, `src/bin/*.rs` and `src/lib.rs` layout are appended when
+    ///   the manifest does not declare them explicitly. Targets are matched by
+    ///   name; the package-named `src/main.rs` binary is only added when the
+    ///   manifest declares no `[[bin]]` at all.
+    ///
+    /// Both the manifest itself and, when the name is inherited, the workspace
+    /// manifest are parsed with [`error::parse_toml`] so a malformed file is
+    /// reported with its path and the offending span rather than a bare message.
+    pub(crate) fn from_path(path: &Path) -> Result<Self, CliError> {
+        let contents = error::read_to_string(path)?;
+        let table = error::parse_toml(path, &contents)?;
+        let mut manifest = Self::from_table(table);
+        let manifest_dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        if manifest.package_name_inherited {
+            manifest.package = resolve_inherited_package_name(manifest_dir)?;
+            manifest.package_name_inherited = false;
+        }
+
+        if manifest.binaries.is_empty() && manifest_dir.join("src/main.rs").is_file() {
+            if let Some(name) = &manifest.package {
+                manifest.binaries.push(name.clone());
+            }
+        }
+
+        if let Ok(entries) = fs::read_dir(manifest_dir.join("src/bin")) {
+            for entry in entries.flatten() {
+                let bin_path = entry.path();
+
+                if bin_path.extension().and_then(|e| e.to_str()) == Some("rs") {
+                    if let Some(stem) = bin_path.file_stem().and_then(|s| s.to_str()) {
+                        if !manifest.binaries.iter().any(|b| b == stem) {
+                            manifest.binaries.push(stem.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        if manifest.library.is_none() && manifest_dir.join("src/lib.rs").is_file() {
+            if let Some(name) = &manifest.package {
+                manifest.library = Some(name.replace('-', "_"));
+            }
+        }
+
+        Ok(manifest)
     }
 }
 
+/// Walk up to the workspace manifest enclosing `manifest_dir` and read the
+/// inherited `[workspace.package] name`. Returns `Ok(None)` when there is no
+/// enclosing workspace manifest or it has no such name; a malformed workspace
+/// manifest is reported as a span-aware [`CliError`].
+fn resolve_inherited_package_name(manifest_dir: &Path) -> Result<Option<String>, CliError> {
+    let Some(workspace_manifest) = find_cargo_parent(&manifest_dir.to_path_buf()) else {
+        return Ok(None);
+    };
+
+    let contents = error::read_to_string(&workspace_manifest)?;
+    let table = error::parse_toml(&workspace_manifest, &contents)?;
+
+    Ok(
+        match table
+            .get("workspace")
+            .and_then(|w| w.get("package"))
+            .and_then(|p| p.get("name"))
+        {
+            Some(Value::String(name)) => Some(name.clone()),
+            _ => None,
+        },
+    )
+}
+
 /// Starting from the current directory, go up a parent until a workspace manifest
 /// or a package manifest is found. If a package manifest is found, continue searching
 /// until reaching the filesystem root.